@@ -4,24 +4,33 @@
 //! horizontal color bands that can span left-to-right for clears.
 //! Uses a lightweight grid snapshot — never clones GameState.
 
-use crate::game::{Cell, GameState, GRAIN_SCALE};
+use crate::game::{Cell, GameState, PieceKind, GRAIN_SCALE};
 use crate::input::Action;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 
 pub struct Bot;
 
-#[derive(Debug, Clone)]
-struct MoveCandidate {
-    score: f32,
-    rotation: u8,
-    target_gx: i32,
-    initial_gx: i32,
-}
+/// A piece pose on the grain grid: (gx, gy, rotation).
+type PieceState = (i32, i32, u8);
+
+/// UCB1 exploration constant for MCTS selection.
+const UCB_C: f32 = 1.4;
+
+/// Further pieces placed during an MCTS rollout before it is scored.
+const ROLLOUT_DEPTH: u32 = 3;
 
 impl Bot {
-    /// Evaluate all reachable (rotation, column) placements for the current piece.
-    /// Returns a sequence of actions (rotations, moves, hard-drop) for the best one.
+    /// Search every pose the current piece can reach by rotating/sliding/soft-dropping,
+    /// evaluate each resting placement, and return the action sequence for the best one.
+    ///
+    /// Reachability is a breadth-first search over `PieceState` rather than a column
+    /// scan followed by a blind hard-drop: that scan can name a target column the piece
+    /// has no legal path to (e.g. sliding under an overhang), and never checks that the
+    /// lateral path down is collision-free. BFS only ever follows edges already verified
+    /// with `can_place_on_grid`, so every reachable resting state is one the piece could
+    /// actually be walked into.
     pub fn find_best_move(
         state: &GameState,
     ) -> VecDeque<Action> {
@@ -31,109 +40,404 @@ impl Bot {
         };
 
         let (gw, gh) = state.playfield.grain_dims();
-        let initial_gx = piece.gx;
         let piece_color = piece.kind.color_index(state.high_color);
+        let weights = Bot::weights();
 
         // Snapshot the grid: 0=empty, color+1=occupied.
         let base_grid = snapshot_grid(&state.playfield, &state.frozen_grains, gw, gh);
 
-        let mut best = MoveCandidate {
-            score: f32::NEG_INFINITY,
-            rotation: 0,
-            target_gx: initial_gx,
-            initial_gx,
+        let Some((start, resting, parent)) = bfs_reachable_poses(&base_grid, gw, gh, piece) else {
+            return VecDeque::new();
         };
 
-        // Column step range (in block units) relative to current piece.
-        let blocks_wide = gw as i32 / GRAIN_SCALE as i32;
-        let min_step = -blocks_wide - 1;
-        let max_step = blocks_wide + 1;
+        // One scratch buffer pair, reused for every candidate in the scan below: each
+        // resting pose resets `working` from `base` by copying bytes into the existing
+        // allocation instead of cloning a fresh `gw*gh` buffer per candidate.
+        let mut scratch = Scratch::new(base_grid);
 
-        for r in 0..4u8 {
-            let mut test_piece = piece.clone();
-            test_piece.rotation = r;
+        let mut best_state = start;
+        let mut best_score = f32::NEG_INFINITY;
+        let mut found_resting = false;
 
-            for step in min_step..=max_step {
-                let target_gx = initial_gx + step * GRAIN_SCALE as i32;
+        for pose in resting {
+            found_resting = true;
+            let grid = scratch.settle_pose(gw, gh, piece, pose, piece_color + 1);
+            let score = evaluate(grid, gw, gh, piece_color, weights);
+            if score > best_score {
+                best_score = score;
+                best_state = pose;
+            }
+        }
 
-                // Quick bounds reject.
-                if target_gx < -(GRAIN_SCALE as i32 * 2)
-                    || target_gx > gw as i32 + GRAIN_SCALE as i32
-                {
-                    continue;
-                }
+        if !found_resting {
+            return VecDeque::new();
+        }
 
-                test_piece.gx = target_gx;
-                test_piece.gy = 0;
+        reconstruct_actions(&parent, start, best_state)
+    }
 
-                // Feasibility at spawn height.
-                if !state
-                    .playfield
-                    .can_place_with_frozen(&test_piece, &state.frozen_grains)
-                {
-                    continue;
-                }
+    /// Monte-Carlo Tree Search over placements of the current piece and the upcoming
+    /// preview queue, running until `budget` elapses. Unlike `find_best_move`, which
+    /// commits greedily to whatever placement scores best for this piece alone, this
+    /// looks through the known next pieces so a setup that only pays off two or three
+    /// placements later (a span that needs a second piece to complete) can outweigh a
+    /// locally-better drop.
+    ///
+    /// Tree nodes are settled flat grids plus an index into the preview queue — never
+    /// `GameState` clones. Selection descends via UCB1, expansion adds one untried
+    /// placement, simulation does a short random rollout scored by `evaluate` on the
+    /// final board, normalized into roughly `[0, 1]` (topping out during rollout scores
+    /// `0`), and backpropagation adds that value up the path. Returns the action
+    /// sequence of the current piece's most-visited placement.
+    pub fn find_best_move_mcts(state: &GameState, budget: Duration) -> VecDeque<Action> {
+        let piece = match state.piece {
+            Some(ref p) => p,
+            None => return VecDeque::new(),
+        };
 
-                // Hard-drop: find landing Y via grid collision.
-                let mut land_y = test_piece.gy;
-                loop {
-                    test_piece.gy = land_y + 1;
-                    if !can_place_on_grid(&base_grid, gw, gh, &test_piece) {
-                        break;
-                    }
-                    land_y += 1;
+        let (gw, gh) = state.playfield.grain_dims();
+        let piece_color = piece.kind.color_index(state.high_color);
+        let base_grid = snapshot_grid(&state.playfield, &state.frozen_grains, gw, gh);
+        let queue: Vec<PieceKind> = state.next_queue.clone();
+
+        let Some((start, root_poses, parent)) = bfs_reachable_poses(&base_grid, gw, gh, piece) else {
+            return VecDeque::new();
+        };
+        if root_poses.is_empty() {
+            return VecDeque::new();
+        }
+
+        // One scratch buffer pair shared by root seeding, `expand_node`, and `rollout`
+        // for the rest of this search: each only ever needs to stamp-and-settle one pose
+        // against a base it re-points at with `set_base`, so the working/alt buffers are
+        // allocated once here and reused across however many thousand iterations the
+        // time budget allows, instead of `settled_grid_for_pose` cloning a fresh `gw*gh`
+        // buffer for every candidate.
+        let mut scratch = Scratch::new(base_grid);
+
+        let mut nodes: Vec<MctsNode> = Vec::new();
+        let mut root_actions: Vec<VecDeque<Action>> = Vec::with_capacity(root_poses.len());
+        for pose in root_poses {
+            let grid = scratch.settle_pose(gw, gh, piece, pose, piece_color + 1).to_vec();
+            nodes.push(MctsNode::new(grid, 0, &queue, piece, gw, gh));
+            root_actions.push(reconstruct_actions(&parent, start, pose));
+        }
+        let roots: Vec<usize> = (0..nodes.len()).collect();
+
+        let mut rng = Rng::seeded();
+        let deadline = Instant::now() + budget;
+
+        while Instant::now() < deadline {
+            let mut cur = select_root(&nodes, &roots);
+            let mut path = vec![cur];
+
+            while nodes[cur].untried.is_empty() && !nodes[cur].children.is_empty() {
+                cur = select_child(&nodes, cur);
+                path.push(cur);
+            }
+
+            if !nodes[cur].untried.is_empty() {
+                if let Some(child) = expand_node(
+                    &mut nodes,
+                    cur,
+                    &queue,
+                    piece,
+                    gw,
+                    gh,
+                    state.high_color,
+                    &mut scratch,
+                ) {
+                    path.push(child);
+                    cur = child;
                 }
-                test_piece.gy = land_y;
-
-                // Clone grid, stamp piece, run simplified settle.
-                let mut grid = base_grid.clone();
-                let stamp_val = piece_color + 1; // grid uses color+1
-                stamp_piece(&mut grid, gw, gh, &test_piece, stamp_val);
-                settle_sand(&mut grid, gw, gh);
-
-                // Evaluate the resulting board.
-                let score = evaluate(&grid, gw, gh, piece_color);
-
-                if score > best.score {
-                    best = MoveCandidate {
-                        score,
-                        rotation: r,
-                        target_gx: target_gx,
-                        initial_gx,
-                    };
+            }
+
+            let value = rollout(
+                &nodes[cur].grid,
+                gw,
+                gh,
+                &queue,
+                nodes[cur].queue_index,
+                piece,
+                state.high_color,
+                Bot::weights(),
+                &mut rng,
+                &mut scratch,
+            );
+
+            for &idx in &path {
+                nodes[idx].visits += 1;
+                nodes[idx].value += value;
+            }
+        }
+
+        let best_root = roots
+            .iter()
+            .copied()
+            .max_by_key(|&i| nodes[i].visits)
+            .expect("roots is non-empty");
+        root_actions[best_root].clone()
+    }
+
+    /// Deterministic multi-ply lookahead over the known preview queue: keep only the
+    /// `beam_width` best boards at each ply, expand each with every resting placement of
+    /// the next queued piece, and re-prune. Unlike `find_best_move_mcts`'s stochastic
+    /// rollouts, this commits to exact evaluation at every node it keeps, trading
+    /// completeness (only `beam_width` lines survive each ply) for determinism.
+    ///
+    /// `depth` counts plies including the current piece, so `depth == 1` behaves like
+    /// `find_best_move` with reach through the beam's first-ply bookkeeping. Falls back
+    /// to `find_best_move` outright when the preview queue is empty, since there is
+    /// nothing to look ahead into. A setup that only pays off two pieces later can still
+    /// outscore a one-shot greedy drop: these flat grids never remove a spanning clear
+    /// once formed, so `evaluate`'s own span-clear term on a later ply's board already
+    /// reflects every span the line has produced so far — no separate per-ply bonus is
+    /// layered on top of it. Everything stays on flat `Vec<u8>` grids — no `GameState`
+    /// is ever cloned.
+    ///
+    /// Each ply scores every `(surviving line, resting pose)` pair through one shared
+    /// `Scratch`, re-pointed at each line's grid with `set_base` — only the
+    /// `beam_width` survivors that make it past pruning are re-settled into an owned
+    /// grid. Without this, a ply with `beam_width` lines and `resting.len()` poses per
+    /// line would heap-allocate a full board for every one of those candidates just to
+    /// throw all but `beam_width` of them away.
+    pub fn find_best_move_beam(
+        state: &GameState,
+        depth: usize,
+        beam_width: usize,
+    ) -> VecDeque<Action> {
+        let piece = match state.piece {
+            Some(ref p) => p,
+            None => return VecDeque::new(),
+        };
+
+        let queue: Vec<PieceKind> = state.next_queue.clone();
+        if queue.is_empty() {
+            return Bot::find_best_move(state);
+        }
+
+        let (gw, gh) = state.playfield.grain_dims();
+        let piece_color = piece.kind.color_index(state.high_color);
+        let weights = Bot::weights();
+        let base_grid = snapshot_grid(&state.playfield, &state.frozen_grains, gw, gh);
+
+        let Some((start, root_poses, parent)) = bfs_reachable_poses(&base_grid, gw, gh, piece) else {
+            return VecDeque::new();
+        };
+        if root_poses.is_empty() {
+            return VecDeque::new();
+        }
+
+        let mut scratch = Scratch::new(base_grid);
+
+        // Ply 0: score every resting placement of the current piece against the shared
+        // scratch, then only materialize an owned grid for the `beam_width` survivors.
+        let mut scored: Vec<(f32, PieceState)> = root_poses
+            .into_iter()
+            .map(|pose| {
+                let grid = scratch.settle_pose(gw, gh, piece, pose, piece_color + 1);
+                (evaluate(grid, gw, gh, piece_color, weights), pose)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        scored.truncate(beam_width.max(1));
+
+        let mut beam: Vec<BeamCandidate> = scored
+            .into_iter()
+            .map(|(_, pose)| {
+                let grid = scratch.settle_pose(gw, gh, piece, pose, piece_color + 1).to_vec();
+                BeamCandidate { grid, last_color: piece_color, actions: reconstruct_actions(&parent, start, pose) }
+            })
+            .collect();
+
+        // Further plies: expand every surviving line with the next queued piece.
+        for ply in 0..depth.saturating_sub(1).min(queue.len()) {
+            let kind = queue[ply];
+            let color_val = kind.color_index(state.high_color) + 1;
+
+            let mut spawn = piece.clone();
+            spawn.kind = kind;
+            spawn.gx = piece.gx;
+            spawn.gy = 0;
+            spawn.rotation = 0;
+
+            // First pass: score every (line, pose) pair without keeping any grid.
+            let mut scored: Vec<(f32, usize, PieceState)> = Vec::new();
+            for (line_idx, candidate) in beam.iter().enumerate() {
+                scratch.set_base(&candidate.grid);
+                let Some((_, resting, _)) = bfs_reachable_poses(&candidate.grid, gw, gh, &spawn)
+                else {
+                    continue; // this line topped out — drop it from the beam
+                };
+                for pose in resting {
+                    let grid = scratch.settle_pose(gw, gh, &spawn, pose, color_val);
+                    let score = evaluate(grid, gw, gh, color_val - 1, weights);
+                    scored.push((score, line_idx, pose));
                 }
             }
+
+            if scored.is_empty() {
+                break; // every surviving line topped out; keep the previous ply's beam
+            }
+            scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+            scored.truncate(beam_width.max(1));
+
+            // Second pass: only the survivors get settled again and kept as owned grids.
+            let mut next_beam = Vec::with_capacity(scored.len());
+            for (_, line_idx, pose) in scored {
+                scratch.set_base(&beam[line_idx].grid);
+                let grid = scratch.settle_pose(gw, gh, &spawn, pose, color_val).to_vec();
+                next_beam.push(BeamCandidate {
+                    grid,
+                    last_color: color_val - 1,
+                    actions: beam[line_idx].actions.clone(),
+                });
+            }
+            beam = next_beam;
         }
 
-        // Build action sequence.
-        build_actions(piece.rotation, best.rotation, best.initial_gx, best.target_gx)
+        beam.into_iter()
+            .max_by(|a, b| {
+                score_candidate(a, gw, gh, weights)
+                    .partial_cmp(&score_candidate(b, gw, gh, weights))
+                    .unwrap()
+            })
+            .map(|c| c.actions)
+            .unwrap_or_default()
+    }
+
+    /// The evaluation weight vector this `Bot` plays with: the vector trained by
+    /// [`train_weights`] and persisted at `WEIGHTS_PATH`, if one exists, otherwise the
+    /// hand-picked [`EvalWeights::default`].
+    fn weights() -> &'static EvalWeights {
+        static TRAINED: std::sync::OnceLock<EvalWeights> = std::sync::OnceLock::new();
+        TRAINED.get_or_init(|| EvalWeights::load(WEIGHTS_PATH))
     }
 }
 
-/// Build the action queue: rotations → lateral moves → hard-drop.
-fn build_actions(cur_rot: u8, target_rot: u8, cur_gx: i32, target_gx: i32) -> VecDeque<Action> {
-    let mut actions = VecDeque::new();
+/// Path `Bot::weights` loads a trained weight vector from, and that `train_weights`
+/// persists its result to.
+const WEIGHTS_PATH: &str = "bot_weights.txt";
 
-    // Rotations (always CW for simplicity).
-    let mut r = cur_rot;
-    while r != target_rot {
-        actions.push_back(Action::RotateCw);
-        r = (r + 1) % 4;
+/// Move `piece` to `pose` and report whether it fits on `grid` there.
+fn pose_fits(
+    grid: &[u8],
+    gw: usize,
+    gh: usize,
+    piece: &mut crate::game::Piece,
+    pose: PieceState,
+) -> bool {
+    piece.gx = pose.0;
+    piece.gy = pose.1;
+    piece.rotation = pose.2;
+    can_place_on_grid(grid, gw, gh, piece)
+}
+
+/// Breadth-first search over every pose `piece` can reach from its current position by
+/// `MoveLeft`/`MoveRight`/`RotateCw`/`RotateCcw`/`SoftDrop` on `grid`. Returns the start
+/// pose, every resting pose reached (one where a further `SoftDrop` is blocked), and
+/// parent pointers for reconstructing the path back to the start. Returns `None` if the
+/// piece doesn't even fit at its current pose (e.g. a top-out).
+fn bfs_reachable_poses(
+    grid: &[u8],
+    gw: usize,
+    gh: usize,
+    piece: &crate::game::Piece,
+) -> Option<(PieceState, Vec<PieceState>, HashMap<PieceState, (PieceState, Action)>)> {
+    let start: PieceState = (piece.gx, piece.gy, piece.rotation);
+    let mut test_piece = piece.clone();
+    if !pose_fits(grid, gw, gh, &mut test_piece, start) {
+        return None;
     }
 
-    // Lateral movement.
-    let diff = target_gx - cur_gx;
-    let steps = diff / GRAIN_SCALE as i32;
-    if steps < 0 {
-        for _ in 0..steps.abs() {
-            actions.push_back(Action::MoveLeft);
+    let mut visited: HashSet<PieceState> = HashSet::new();
+    let mut parent: HashMap<PieceState, (PieceState, Action)> = HashMap::new();
+    let mut frontier: VecDeque<PieceState> = VecDeque::new();
+    let mut resting = Vec::new();
+    visited.insert(start);
+    frontier.push_back(start);
+
+    while let Some(cur) = frontier.pop_front() {
+        let edges = [
+            (Action::MoveLeft, (cur.0 - GRAIN_SCALE as i32, cur.1, cur.2)),
+            (Action::MoveRight, (cur.0 + GRAIN_SCALE as i32, cur.1, cur.2)),
+            (Action::RotateCw, (cur.0, cur.1, (cur.2 + 1) % 4)),
+            (Action::RotateCcw, (cur.0, cur.1, (cur.2 + 3) % 4)),
+            (Action::SoftDrop, (cur.0, cur.1 + 1, cur.2)),
+        ];
+
+        // A pose is a resting placement when the one soft-drop edge is blocked.
+        let mut can_drop = false;
+        for (action, next) in edges {
+            if !pose_fits(grid, gw, gh, &mut test_piece, next) {
+                continue;
+            }
+            if matches!(action, Action::SoftDrop) {
+                can_drop = true;
+            }
+            if visited.insert(next) {
+                parent.insert(next, (cur, action));
+                frontier.push_back(next);
+            }
         }
-    } else {
-        for _ in 0..steps {
-            actions.push_back(Action::MoveRight);
+
+        if !can_drop {
+            resting.push(cur);
         }
     }
 
+    Some((start, resting, parent))
+}
+
+/// Stamp `piece` at `pose` onto a copy of `base_grid` and settle it, without touching
+/// `base_grid` itself.
+fn settled_grid_for_pose(
+    base_grid: &[u8],
+    gw: usize,
+    gh: usize,
+    piece: &crate::game::Piece,
+    pose: PieceState,
+    color_val: u8,
+) -> Vec<u8> {
+    let mut grid = base_grid.to_vec();
+    let mut test_piece = piece.clone();
+    test_piece.gx = pose.0;
+    test_piece.gy = pose.1;
+    test_piece.rotation = pose.2;
+    stamp_piece(&mut grid, gw, gh, &test_piece, color_val);
+    settle_sand(&mut grid, gw, gh);
+    grid
+}
+
+/// Settle `piece` at `pose` on `base_grid` and evaluate the resulting board.
+fn score_pose(
+    base_grid: &[u8],
+    gw: usize,
+    gh: usize,
+    piece: &crate::game::Piece,
+    pose: PieceState,
+    piece_color: u8,
+    weights: &EvalWeights,
+) -> f32 {
+    let grid = settled_grid_for_pose(base_grid, gw, gh, piece, pose, piece_color + 1);
+    evaluate(&grid, gw, gh, piece_color, weights)
+}
+
+/// Walk `parent` pointers from `goal` back to `start`, turning the path into an
+/// action queue (rotations/moves/soft-drops interleaved as the search found them),
+/// terminating in a `HardDrop` to commit the resting placement.
+fn reconstruct_actions(
+    parent: &HashMap<PieceState, (PieceState, Action)>,
+    start: PieceState,
+    goal: PieceState,
+) -> VecDeque<Action> {
+    let mut actions = VecDeque::new();
+    let mut cur = goal;
+    while cur != start {
+        let (prev, action) = parent.get(&cur).expect("every non-start state has a parent").clone();
+        actions.push_front(action);
+        cur = prev;
+    }
     actions.push_back(Action::HardDrop);
     actions
 }
@@ -242,6 +546,116 @@ fn settle_sand(grid: &mut [u8], gw: usize, gh: usize) {
     }
 }
 
+/// One settle pass, reading from `front` and writing into `back` (which starts each
+/// pass as a copy of `front`). Splitting this out of `settle_sand` lets `Scratch` ping-
+/// pong between two owned buffers instead of mutating one in place, and turns "did
+/// anything move" into the return value instead of a loop-scoped flag.
+fn settle_pass(front: &[u8], back: &mut [u8], gw: usize, gh: usize, pass: u32) -> bool {
+    back.copy_from_slice(front);
+    let left_first = pass % 2 == 0;
+    let mut moved = false;
+
+    for y in (0..gh.saturating_sub(1)).rev() {
+        for x in 0..gw {
+            let idx = y * gw + x;
+            let c = front[idx];
+            if c == 0 {
+                continue;
+            }
+            let below = (y + 1) * gw + x;
+            if back[below] == 0 {
+                back[below] = c;
+                back[idx] = 0;
+                moved = true;
+                continue;
+            }
+            let can_left = x > 0 && back[(y + 1) * gw + x - 1] == 0;
+            let can_right = x + 1 < gw && back[(y + 1) * gw + x + 1] == 0;
+            let go_left = if can_left && can_right { left_first } else { can_left };
+            if go_left {
+                back[(y + 1) * gw + x - 1] = c;
+                back[idx] = 0;
+                moved = true;
+            } else if can_right {
+                back[(y + 1) * gw + x + 1] = c;
+                back[idx] = 0;
+                moved = true;
+            }
+        }
+    }
+    moved
+}
+
+/// A reusable pair of scratch grids for a placement scan that evaluates many candidate
+/// poses from the same base snapshot: `base` is the immutable starting board, `working`
+/// (and its ping-pong partner `alt`) hold each candidate's stamped-and-settled board.
+/// `settle_pose` resets `working` by copying bytes into the existing allocation rather
+/// than cloning a fresh `gw*gh` buffer per candidate, so a full scan allocates only
+/// once, at construction.
+struct Scratch {
+    base: Vec<u8>,
+    working: Vec<u8>,
+    alt: Vec<u8>,
+}
+
+impl Scratch {
+    fn new(base: Vec<u8>) -> Self {
+        let working = base.clone();
+        let alt = base.clone();
+        Scratch { base, working, alt }
+    }
+
+    /// Point this scratch at a different base snapshot (e.g. another tree node's or
+    /// beam candidate's settled grid) by copying bytes into the existing allocation,
+    /// so the same `Scratch` can be walked across many bases across a whole search
+    /// without reallocating.
+    fn set_base(&mut self, grid: &[u8]) {
+        self.base.copy_from_slice(grid);
+    }
+
+    /// The current base snapshot `settle_pose` settles candidates against.
+    fn base(&self) -> &[u8] {
+        &self.base
+    }
+
+    /// Reset to the base snapshot, stamp `piece` at `pose`, and settle — reusing this
+    /// scratch's buffers. Returns the settled grid.
+    fn settle_pose(
+        &mut self,
+        gw: usize,
+        gh: usize,
+        piece: &crate::game::Piece,
+        pose: PieceState,
+        color_val: u8,
+    ) -> &[u8] {
+        self.working.copy_from_slice(&self.base);
+
+        let mut test_piece = piece.clone();
+        test_piece.gx = pose.0;
+        test_piece.gy = pose.1;
+        test_piece.rotation = pose.2;
+        stamp_piece(&mut self.working, gw, gh, &test_piece, color_val);
+
+        for pass in 0..80u32 {
+            let moved = settle_pass(&self.working, &mut self.alt, gw, gh, pass);
+            // Vec::swap only swaps the (pointer, len, cap) header — no data is copied.
+            std::mem::swap(&mut self.working, &mut self.alt);
+            if !moved {
+                break;
+            }
+        }
+        &self.working
+    }
+
+    /// Promote the last `settle_pose` result to be the base for the next call, by
+    /// swapping the (pointer, len, cap) header rather than copying grid data — used to
+    /// chain several placements onto the same scratch without ever materializing an
+    /// owned `Vec` for the intermediate boards.
+    fn commit(&mut self) {
+        std::mem::swap(&mut self.base, &mut self.working);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Board evaluation — the core brain of the bot
 // ---------------------------------------------------------------------------
@@ -252,25 +666,14 @@ fn evaluate(
     gw: usize,
     gh: usize,
     placed_color: u8,
+    weights: &EvalWeights,
 ) -> f32 {
     let mut score: f32 = 0.0;
     let placed_val = placed_color + 1;
 
-    // --- Scoring Constants ---
-    const W_SPAN_CLEAR: f32 = 50.0;
-    const W_HOLES: f32 = 8.0;
-    const W_MAX_HEIGHT: f32 = 3.5;
-    const W_AGG_HEIGHT: f32 = 0.15;
-    const W_BUMPINESS: f32 = 1.5;
-    const W_H_ADJACENCY: f32 = 0.8;
-    const W_V_ADJACENCY: f32 = 0.2;
-    const W_PROXIMITY: f32 = 0.5;
-    const W_REACH: f32 = 5.0;
-    const W_DANGER: f32 = 100.0;
-
     // --- 1. Spanning clears (instant massive reward) ---
     let clears = count_spanning_clears(grid, gw, gh);
-    score += clears as f32 * W_SPAN_CLEAR;
+    score += clears as f32 * weights.span_clear;
 
     // --- 2. Column heights, holes, bumpiness ---
     let mut col_heights = vec![0usize; gw];
@@ -297,10 +700,10 @@ fn evaluate(
         .map(|w| (w[0] as i32 - w[1] as i32).abs())
         .sum();
 
-    score -= holes as f32 * W_HOLES;
-    score -= max_height as f32 * W_MAX_HEIGHT;
-    score -= agg_height as f32 * W_AGG_HEIGHT;
-    score -= bumpiness as f32 * W_BUMPINESS;
+    score -= holes as f32 * weights.holes;
+    score -= max_height as f32 * weights.max_height;
+    score -= agg_height as f32 * weights.agg_height;
+    score -= bumpiness as f32 * weights.bumpiness;
 
     // --- 3. Same-color adjacency (horizontal only — we want horizontal bands) ---
     // Horizontal adjacency matters much more than vertical for spanning paths.
@@ -320,22 +723,22 @@ fn evaluate(
         }
     }
     // Horizontal adjacency is king for spanning paths.
-    score += h_adj as f32 * W_H_ADJACENCY;
-    score += v_adj as f32 * W_V_ADJACENCY;
+    score += h_adj as f32 * weights.h_adjacency;
+    score += v_adj as f32 * weights.v_adjacency;
 
     // --- 4. Placed piece proximity to same-color sand ---
     // Reward placing near existing sand of the same color.
     // This encourages color clustering which leads to spans.
     let proximity_bonus = same_color_proximity(grid, gw, gh, placed_val);
-    score += proximity_bonus * W_PROXIMITY;
+    score += proximity_bonus * weights.proximity;
 
     // --- 5. Horizontal reach per color (how close each color is to spanning) ---
-    let reach_bonus = color_reach_bonus(grid, gw, gh, W_REACH);
+    let reach_bonus = color_reach_bonus(grid, gw, gh, weights.reach);
     score += reach_bonus;
 
     // --- 6. Danger zone ---
     if max_height > gh.saturating_sub(10) {
-        score -= W_DANGER;
+        score -= weights.danger;
     }
 
     score
@@ -455,3 +858,647 @@ fn color_reach_bonus(grid: &[u8], gw: usize, gh: usize, base_reach: f32) -> f32
     }
     bonus
 }
+
+// ---------------------------------------------------------------------------
+// MCTS search
+// ---------------------------------------------------------------------------
+
+/// One node in the MCTS tree: a settled grid reached after placing some pieces,
+/// plus `queue_index`, the offset into the preview queue of the piece this node's
+/// untried placements belong to. `piece` is a spawn-pose template of that piece's
+/// kind, used to realize `untried` poses into settled child grids.
+struct MctsNode {
+    grid: Vec<u8>,
+    queue_index: usize,
+    piece: crate::game::Piece,
+    untried: Vec<PieceState>,
+    children: Vec<usize>,
+    visits: u32,
+    value: f32,
+}
+
+impl MctsNode {
+    fn new(
+        grid: Vec<u8>,
+        queue_index: usize,
+        queue: &[PieceKind],
+        root_piece: &crate::game::Piece,
+        gw: usize,
+        gh: usize,
+    ) -> Self {
+        let kind = queue.get(queue_index).copied().unwrap_or(root_piece.kind);
+        let mut piece = root_piece.clone();
+        piece.kind = kind;
+        piece.gx = root_piece.gx;
+        piece.gy = 0;
+        piece.rotation = 0;
+
+        let untried = bfs_reachable_poses(&grid, gw, gh, &piece)
+            .map(|(_, resting, _)| resting)
+            .unwrap_or_default();
+
+        MctsNode { grid, queue_index, piece, untried, children: Vec::new(), visits: 0, value: 0.0 }
+    }
+}
+
+/// UCB1 score for a child given its parent's total visit count.
+fn ucb1(child_value: f32, child_visits: u32, parent_visits: u32) -> f32 {
+    if child_visits == 0 {
+        return f32::INFINITY;
+    }
+    let exploitation = child_value / child_visits as f32;
+    let exploration = UCB_C * ((parent_visits as f32).ln() / child_visits as f32).sqrt();
+    exploitation + exploration
+}
+
+/// Pick the root child to descend into, treating the sum of root visits as the
+/// (virtual) parent visit count since root nodes have no single shared parent node.
+fn select_root(nodes: &[MctsNode], roots: &[usize]) -> usize {
+    let parent_visits: u32 = roots.iter().map(|&i| nodes[i].visits).sum();
+    *roots
+        .iter()
+        .max_by(|&&a, &&b| {
+            ucb1(nodes[a].value, nodes[a].visits, parent_visits.max(1))
+                .partial_cmp(&ucb1(nodes[b].value, nodes[b].visits, parent_visits.max(1)))
+                .unwrap()
+        })
+        .expect("roots is non-empty")
+}
+
+/// Pick the best-UCB1 child of an already-fully-tried node.
+fn select_child(nodes: &[MctsNode], idx: usize) -> usize {
+    let parent_visits = nodes[idx].visits;
+    *nodes[idx]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| {
+            ucb1(nodes[a].value, nodes[a].visits, parent_visits)
+                .partial_cmp(&ucb1(nodes[b].value, nodes[b].visits, parent_visits))
+                .unwrap()
+        })
+        .expect("node has untried poses exhausted into children")
+}
+
+/// Pop one untried placement from `nodes[idx]`, settle it, and attach it as a new child.
+/// `scratch` is re-pointed at this node's grid with `set_base` rather than cloned, so the
+/// only grid this call allocates is the new child's own persisted one.
+fn expand_node(
+    nodes: &mut Vec<MctsNode>,
+    idx: usize,
+    queue: &[PieceKind],
+    root_piece: &crate::game::Piece,
+    gw: usize,
+    gh: usize,
+    high_color: u8,
+    scratch: &mut Scratch,
+) -> Option<usize> {
+    let pose = nodes[idx].untried.pop()?;
+    let template = nodes[idx].piece.clone();
+    let color_val = template.kind.color_index(high_color) + 1;
+    scratch.set_base(&nodes[idx].grid);
+    let child_grid = scratch.settle_pose(gw, gh, &template, pose, color_val).to_vec();
+    let child_queue_index = nodes[idx].queue_index + 1;
+    let child = MctsNode::new(child_grid, child_queue_index, queue, root_piece, gw, gh);
+
+    nodes.push(child);
+    let child_idx = nodes.len() - 1;
+    nodes[idx].children.push(child_idx);
+    Some(child_idx)
+}
+
+/// Play `ROLLOUT_DEPTH` further pieces at uniformly random legal placements and score
+/// the resulting board with `evaluate`, squashed into roughly `[0, 1]`. A top-out
+/// mid-rollout scores `0` outright.
+///
+/// These flat grids never remove a spanning clear once formed, so `evaluate`'s own
+/// span-clear term on the final board already counts every span the rollout produced
+/// along the way — it is not re-added per step, which would count a clear that is
+/// still sitting on the board once for every remaining step after it formed.
+///
+/// `scratch` is re-pointed at `start_grid` and then walked forward one placement at a
+/// time via `settle_pose`/`commit`: only the final board is scored and no intermediate
+/// board is kept, so none of the `ROLLOUT_DEPTH` steps needs its own heap-allocated grid.
+fn rollout(
+    start_grid: &[u8],
+    gw: usize,
+    gh: usize,
+    queue: &[PieceKind],
+    mut queue_index: usize,
+    root_piece: &crate::game::Piece,
+    high_color: u8,
+    weights: &EvalWeights,
+    rng: &mut Rng,
+    scratch: &mut Scratch,
+) -> f32 {
+    scratch.set_base(start_grid);
+    let mut last_color = root_piece.kind.color_index(high_color);
+
+    for _ in 0..ROLLOUT_DEPTH {
+        let kind = queue.get(queue_index).copied().unwrap_or(root_piece.kind);
+        queue_index += 1;
+
+        let mut piece = root_piece.clone();
+        piece.kind = kind;
+        piece.gx = root_piece.gx;
+        piece.gy = 0;
+        piece.rotation = 0;
+
+        let Some((_, resting, _)) = bfs_reachable_poses(scratch.base(), gw, gh, &piece) else {
+            return 0.0;
+        };
+        if resting.is_empty() {
+            return 0.0;
+        }
+
+        let pose = resting[rng.gen_range(resting.len())];
+        last_color = kind.color_index(high_color);
+        scratch.settle_pose(gw, gh, &piece, pose, last_color + 1);
+        scratch.commit();
+    }
+
+    let score = evaluate(scratch.base(), gw, gh, last_color, weights);
+    // Logistic squash keeps the backpropagated value in (0, 1), centred on a raw score of 0.
+    1.0 / (1.0 + (-score / 100.0).exp())
+}
+
+// ---------------------------------------------------------------------------
+// Beam search
+// ---------------------------------------------------------------------------
+
+/// One surviving line in `Bot::find_best_move_beam`'s beam: the settled grid reached
+/// so far, the color of the last piece placed (for `evaluate`'s proximity term), and
+/// the first-ply action sequence that needs replaying if this line wins at the final
+/// ply.
+struct BeamCandidate {
+    grid: Vec<u8>,
+    last_color: u8,
+    actions: VecDeque<Action>,
+}
+
+/// A candidate's standing score. Since these flat grids never remove a spanning
+/// clear once formed, `evaluate`'s own span-clear term on the current board already
+/// counts every span the line has ever produced — there is nothing left for this
+/// function to add on top of it without double-counting.
+fn score_candidate(candidate: &BeamCandidate, gw: usize, gh: usize, weights: &EvalWeights) -> f32 {
+    evaluate(&candidate.grid, gw, gh, candidate.last_color, weights)
+}
+
+/// Minimal xorshift64* generator for rollout placement sampling and training-time
+/// mutation — this only needs a fast, decently-distributed stream, not cryptographic
+/// quality.
+struct Rng(u64);
+
+impl Rng {
+    fn seeded() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Rng(nanos | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn gen_range(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() as usize) % n
+        }
+    }
+
+    /// Uniform float in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Sample from `N(0, sigma)` via Box-Muller.
+    fn gaussian(&mut self, sigma: f32) -> f32 {
+        let u1 = self.next_f32().max(1e-9);
+        let u2 = self.next_f32();
+        let mag = sigma * (-2.0 * u1.ln()).sqrt();
+        mag * (2.0 * std::f32::consts::PI * u2).cos()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation weights and self-tuning trainer
+// ---------------------------------------------------------------------------
+
+/// The tunable weights `evaluate` scores a board with. Threading these through instead
+/// of hard-coded constants lets [`train_weights`] learn a vector by self-play, and lets
+/// players regenerate weights for a different board size or color count rather than
+/// hand-tuning magic numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvalWeights {
+    pub span_clear: f32,
+    pub holes: f32,
+    pub max_height: f32,
+    pub agg_height: f32,
+    pub bumpiness: f32,
+    pub h_adjacency: f32,
+    pub v_adjacency: f32,
+    pub proximity: f32,
+    pub reach: f32,
+    pub danger: f32,
+}
+
+impl Default for EvalWeights {
+    /// The hand-picked constants `evaluate` used before weights became tunable.
+    fn default() -> Self {
+        EvalWeights {
+            span_clear: 50.0,
+            holes: 8.0,
+            max_height: 3.5,
+            agg_height: 0.15,
+            bumpiness: 1.5,
+            h_adjacency: 0.8,
+            v_adjacency: 0.2,
+            proximity: 0.5,
+            reach: 5.0,
+            danger: 100.0,
+        }
+    }
+}
+
+impl EvalWeights {
+    const FIELDS: usize = 10;
+
+    fn to_array(self) -> [f32; Self::FIELDS] {
+        [
+            self.span_clear,
+            self.holes,
+            self.max_height,
+            self.agg_height,
+            self.bumpiness,
+            self.h_adjacency,
+            self.v_adjacency,
+            self.proximity,
+            self.reach,
+            self.danger,
+        ]
+    }
+
+    fn from_array(a: [f32; Self::FIELDS]) -> Self {
+        EvalWeights {
+            span_clear: a[0],
+            holes: a[1],
+            max_height: a[2],
+            agg_height: a[3],
+            bumpiness: a[4],
+            h_adjacency: a[5],
+            v_adjacency: a[6],
+            proximity: a[7],
+            reach: a[8],
+            danger: a[9],
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut values = [0f32; Self::FIELDS];
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        for slot in values.iter_mut() {
+            *slot = lines.next()?.trim().parse().ok()?;
+        }
+        Some(Self::from_array(values))
+    }
+
+    fn to_text(self) -> String {
+        self.to_array()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Load a trained weight vector from `path`, falling back to the hand-picked
+    /// [`EvalWeights::default`] if the file is missing or malformed.
+    pub fn load(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Persist this weight vector so a future `Bot::weights` call picks it up.
+    pub fn save(self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+}
+
+/// Pieces placed per headless training game before it is cut off as a draw.
+const MAX_TRAINING_PIECES: u32 = 300;
+
+/// Headless games averaged per individual's fitness score each generation.
+const GAMES_PER_INDIVIDUAL: u64 = 3;
+
+/// Train an `EvalWeights` vector by self-play and persist the fittest one found to
+/// `WEIGHTS_PATH`, where `Bot::weights` will pick it up on a future run.
+///
+/// Maintains a population of 30 weight vectors. Each individual's fitness is the
+/// average number of spanning clears its bot racks up across `GAMES_PER_INDIVIDUAL`
+/// seeded headless games before topping out or hitting `MAX_TRAINING_PIECES`. The next
+/// generation is produced by tournament selection, uniform crossover, and Gaussian
+/// mutation with sigma annealed from 0.3 down to 0.05 over `generations`. Returns the
+/// best vector seen across the whole run (not just the final generation's).
+///
+/// `seed` drives every operator (initial population, tournament draws, crossover,
+/// mutation) via a single `Rng`, so two calls with the same `seed`/`generations`/`gw`/
+/// `gh` reproduce the same run — this is what makes the optimization reproducible
+/// rather than guess-and-check, not just the per-game fitness seeding.
+///
+/// A run can take `generations * POPULATION * GAMES_PER_INDIVIDUAL` headless games to
+/// complete, so a failure to persist the result at the end (read-only working
+/// directory, full disk) is reported back rather than silently discarded — the caller
+/// still gets `best` either way, since the trained vector is useful in-process even if
+/// it couldn't be written to disk.
+pub fn train_weights(
+    generations: usize,
+    gw: usize,
+    gh: usize,
+    seed: u64,
+) -> (EvalWeights, std::io::Result<()>) {
+    const POPULATION: usize = 30;
+    const TOURNAMENT_SIZE: usize = 4;
+    const MUTATION_RATE: f32 = 0.1;
+
+    let mut rng = Rng(seed | 1);
+    let mut population: Vec<EvalWeights> =
+        (0..POPULATION).map(|_| random_weights(&mut rng)).collect();
+    population[0] = EvalWeights::default(); // seed the pool with the known-good baseline
+
+    let mut best = population[0];
+    let mut best_fitness = f32::NEG_INFINITY;
+
+    for gen in 0..generations.max(1) {
+        let sigma = (0.3 * (1.0 - gen as f32 / generations.max(1) as f32)).max(0.05);
+
+        let fitness: Vec<f32> = population
+            .iter()
+            .map(|w| {
+                let total: f32 = (0..GAMES_PER_INDIVIDUAL)
+                    .map(|game_seed| play_headless_game(w, gw, gh, game_seed + gen as u64 * 1000) as f32)
+                    .sum();
+                total / GAMES_PER_INDIVIDUAL as f32
+            })
+            .collect();
+
+        if let Some((idx, &f)) = fitness
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+        {
+            if f > best_fitness {
+                best_fitness = f;
+                best = population[idx];
+            }
+        }
+
+        population = (0..POPULATION)
+            .map(|_| {
+                let parent_a = tournament_select(&population, &fitness, TOURNAMENT_SIZE, &mut rng);
+                let parent_b = tournament_select(&population, &fitness, TOURNAMENT_SIZE, &mut rng);
+                let child = crossover(parent_a, parent_b, &mut rng);
+                mutate(child, MUTATION_RATE, sigma, &mut rng)
+            })
+            .collect();
+    }
+
+    let saved = best.save(WEIGHTS_PATH);
+    (best, saved)
+}
+
+/// Ceiling every weight is clamped to. Nothing in the fitness function penalizes large
+/// magnitudes, so a term like `span_clear` has direct selection pressure to grow
+/// unbounded over many generations; without a ceiling it could eventually drift to
+/// `inf`, and `evaluate`'s `0.0 * weight` terms (e.g. zero holes on a clean board) would
+/// then produce `NaN`, which every `.partial_cmp(...).unwrap()` in the search functions
+/// panics on.
+const MAX_WEIGHT: f32 = 1000.0;
+
+/// A random weight vector, scattered around the hand-picked defaults rather than
+/// drawn from nowhere, so the initial population already plays reasonably.
+fn random_weights(rng: &mut Rng) -> EvalWeights {
+    EvalWeights::from_array(
+        EvalWeights::default()
+            .to_array()
+            .map(|base| (base + rng.gaussian(base.abs().max(1.0) * 0.5)).clamp(0.0, MAX_WEIGHT)),
+    )
+}
+
+fn tournament_select(
+    population: &[EvalWeights],
+    fitness: &[f32],
+    size: usize,
+    rng: &mut Rng,
+) -> EvalWeights {
+    (0..size)
+        .map(|_| rng.gen_range(population.len()))
+        .max_by(|&a, &b| fitness[a].partial_cmp(&fitness[b]).unwrap())
+        .map(|idx| population[idx])
+        .expect("tournament size is non-zero")
+}
+
+fn crossover(a: EvalWeights, b: EvalWeights, rng: &mut Rng) -> EvalWeights {
+    let (a, b) = (a.to_array(), b.to_array());
+    let mut child = [0f32; EvalWeights::FIELDS];
+    for i in 0..EvalWeights::FIELDS {
+        child[i] = if rng.next_f32() < 0.5 { a[i] } else { b[i] };
+    }
+    EvalWeights::from_array(child)
+}
+
+fn mutate(weights: EvalWeights, rate: f32, sigma: f32, rng: &mut Rng) -> EvalWeights {
+    let mut values = weights.to_array();
+    for v in values.iter_mut() {
+        if rng.next_f32() < rate {
+            *v = (*v + rng.gaussian(sigma)).clamp(0.0, MAX_WEIGHT);
+        }
+    }
+    EvalWeights::from_array(values)
+}
+
+/// Play one headless game with a fixed weight vector driving every placement, used to
+/// measure fitness during training. Returns the number of spanning clears triggered
+/// before the bot topped out or the piece cap was reached.
+fn play_headless_game(weights: &EvalWeights, gw: usize, gh: usize, seed: u64) -> u32 {
+    let mut grid = vec![0u8; gw * gh];
+    let mut rng = Rng(seed | 1);
+    let mut total_clears = 0u32;
+
+    for _ in 0..MAX_TRAINING_PIECES {
+        let kind = random_kind(&mut rng);
+        let piece = spawn_piece(kind, gw);
+
+        let Some((_, resting, _)) = bfs_reachable_poses(&grid, gw, gh, &piece) else {
+            break; // topped out
+        };
+        if resting.is_empty() {
+            break;
+        }
+
+        let color = kind.color_index(0);
+        let mut best_pose = resting[0];
+        let mut best_score = f32::NEG_INFINITY;
+        for &pose in &resting {
+            let score = score_pose(&grid, gw, gh, &piece, pose, color, weights);
+            if score > best_score {
+                best_score = score;
+                best_pose = pose;
+            }
+        }
+
+        let mut next_grid = settled_grid_for_pose(&grid, gw, gh, &piece, best_pose, color + 1);
+        total_clears += clear_spans(&mut next_grid, gw, gh);
+        grid = next_grid;
+    }
+
+    total_clears
+}
+
+/// A uniformly random piece kind, used to drive headless training games.
+fn random_kind(rng: &mut Rng) -> PieceKind {
+    PieceKind::from_index(rng.gen_range(PieceKind::COUNT))
+}
+
+/// A fresh piece of `kind` at its spawn pose, centred on the board. Training has no
+/// `GameState` to clone a template piece from, unlike the rest of the search code.
+fn spawn_piece(kind: PieceKind, gw: usize) -> crate::game::Piece {
+    let spawn_gx = (gw as i32 / 2) - (GRAIN_SCALE as i32 / 2);
+    crate::game::Piece::spawn(kind, spawn_gx)
+}
+
+/// Find and zero out every same-color component that spans left-to-right, then settle
+/// what remains. Used only by the headless trainer: the real game's own clear handling
+/// lives with `GameState`, which this module never touches. Returns the clear count.
+fn clear_spans(grid: &mut [u8], gw: usize, gh: usize) -> u32 {
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+    let mut to_clear = Vec::new();
+    let mut cleared = 0u32;
+
+    for color in 1..=6u8 {
+        for start_y in 0..gh {
+            let pos = (0usize, start_y);
+            if grid[start_y * gw] != color || visited.contains(&pos) {
+                continue;
+            }
+
+            let mut stack = vec![pos];
+            let mut component = vec![pos];
+            visited.insert(pos);
+            let mut touches_right = false;
+
+            while let Some((x, y)) = stack.pop() {
+                if x == gw - 1 {
+                    touches_right = true;
+                }
+                for &(dx, dy) in &NEIGHBOURS_8 {
+                    let nx = x as i32 + dx;
+                    let ny = y as i32 + dy;
+                    if nx >= 0 && nx < gw as i32 && ny >= 0 && ny < gh as i32 {
+                        let npos = (nx as usize, ny as usize);
+                        if grid[npos.1 * gw + npos.0] == color && !visited.contains(&npos) {
+                            visited.insert(npos);
+                            stack.push(npos);
+                            component.push(npos);
+                        }
+                    }
+                }
+            }
+
+            if touches_right {
+                cleared += 1;
+                to_clear.extend(component);
+            }
+        }
+    }
+
+    for (x, y) in to_clear {
+        grid[y * gw + x] = 0;
+    }
+    if cleared > 0 {
+        settle_sand(grid, gw, gh);
+    }
+    cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The inclusive grain-space bounding box covering every origin's full
+    /// `GRAIN_SCALE x GRAIN_SCALE` footprint: `(min_x, max_x, min_y, max_y)`.
+    fn footprint_bounds(origins: &[(i32, i32)]) -> (i32, i32, i32, i32) {
+        let scale = GRAIN_SCALE as i32;
+        let min_x = origins.iter().map(|&(x, _)| x).min().unwrap();
+        let max_x = origins.iter().map(|&(x, _)| x).max().unwrap() + scale - 1;
+        let min_y = origins.iter().map(|&(_, y)| y).min().unwrap();
+        let max_y = origins.iter().map(|&(_, y)| y).max().unwrap() + scale - 1;
+        (min_x, max_x, min_y, max_y)
+    }
+
+    /// A naive per-column scan picks a target column, then hard-drops straight down
+    /// from spawn height — it never checks whether the lateral path to get there, or
+    /// past an obstruction partway down, is actually collision-free. Here the only way
+    /// to reach the floor beneath a low roof is to drop one cell to the side first
+    /// (where there's no roof), continue past the roof's height, then slide back under
+    /// it — a tuck a straight hard-drop in either column would never produce. BFS,
+    /// which only follows edges already verified with `can_place_on_grid`, should find
+    /// both the shallow resting pose on top of the roof and the deeper tucked one.
+    #[test]
+    fn bfs_reaches_tuck_under_overhang_that_hard_drop_would_miss() {
+        let gw = 12;
+        let gh = 14;
+        let scale = GRAIN_SCALE as i32;
+        let piece = spawn_piece(PieceKind::from_index(0), gw);
+
+        let origins: Vec<(i32, i32)> = piece.cell_grain_origins().collect();
+        let (min_x, max_x, _, max_y) = footprint_bounds(&origins);
+
+        // One cell step to the side of the spawn footprint, with nothing above it —
+        // the column the piece must pass through on its way into the tucked pocket.
+        let side_min_x = min_x + scale;
+        let side_max_x = max_x + scale;
+        assert!(side_max_x < gw as i32, "test board too narrow for this piece");
+
+        let roof_y = max_y + 1 + scale;
+        let mut grid = vec![0u8; gw * gh];
+
+        // Floor across the whole board.
+        for x in 0..gw {
+            grid[(gh - 1) * gw + x] = 1;
+        }
+        // A low roof directly over the spawn column only, leaving the column one cell
+        // to the side completely open.
+        for x in min_x..=max_x {
+            grid[roof_y as usize * gw + x as usize] = 1;
+        }
+        assert!(
+            (side_min_x..=side_max_x).all(|x| grid[roof_y as usize * gw + x as usize] == 0),
+            "side column must stay clear of the roof"
+        );
+
+        let Some((start, resting, _)) = bfs_reachable_poses(&grid, gw, gh, &piece) else {
+            panic!("piece should fit at its own spawn pose");
+        };
+        assert_eq!(start, (piece.gx, piece.gy, piece.rotation));
+
+        assert!(
+            resting.iter().any(|p| p.1 < roof_y),
+            "expected a shallow resting pose on top of the roof"
+        );
+        assert!(
+            resting.iter().any(|p| p.1 > roof_y),
+            "BFS should also find a deeper resting pose below the roof, reached by \
+             sliding through the open column beside it — a straight hard-drop in either \
+             column alone can't reach it"
+        );
+    }
+}